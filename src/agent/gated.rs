@@ -0,0 +1,125 @@
+//! `SupportedAgentConfigurations::Gated`: a hands-off mode that keeps the coding agent iterating
+//! until `commands.test` passes, then commits and pushes through `GitAgentEnvironment` instead
+//! of leaving the change for a human to advance turn by turn.
+//!
+//! Modeled as a small state machine: `Editing -> Validating -> Advancing -> Done`, with a failed
+//! `Validating` looping back to `Editing` carrying the failing diagnostics, bounded by a
+//! configurable retry count.
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use swiftide::traits::ToolExecutor;
+
+use crate::commands::Responder;
+
+use super::{git_agent_environment::GitAgentEnvironment, running_agent::RunningAgent, session::Session, tools};
+
+/// Where a gated run currently is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GatedState {
+    Editing,
+    Validating,
+    Advancing,
+    Done,
+}
+
+/// Drives a coding agent through the gated state machine until `commands.test` passes (then
+/// commits and pushes) or the retry budget is exhausted.
+pub struct GatedRunner {
+    max_retries: usize,
+}
+
+impl GatedRunner {
+    #[must_use]
+    pub fn new(max_retries: usize) -> Self {
+        Self {
+            max_retries: max_retries.max(1),
+        }
+    }
+
+    pub async fn run(
+        &self,
+        session: &Arc<Session>,
+        responder: &Arc<dyn Responder>,
+        executor: &Arc<dyn ToolExecutor>,
+        agent_environment: &GitAgentEnvironment,
+        coding_agent: &RunningAgent,
+    ) -> Result<()> {
+        let test_command = session
+            .repository
+            .config()
+            .commands
+            .test
+            .clone()
+            .context("Gated mode requires `commands.test` to be configured")?;
+
+        let mut state = GatedState::Editing;
+        let mut attempt = 0;
+        let mut diagnostics: Option<String> = None;
+
+        loop {
+            match state {
+                GatedState::Editing => {
+                    attempt += 1;
+                    responder
+                        .update(&format!(
+                            "gated: editing (attempt {attempt}/{})",
+                            self.max_retries
+                        ))
+                        .await;
+
+                    match diagnostics.take() {
+                        Some(failure) => {
+                            coding_agent
+                                .query(&format!(
+                                    "The previous attempt failed `{test_command}`:\n\n{failure}\n\nFix it and try again."
+                                ))
+                                .await?;
+                        }
+                        None => coding_agent.run().await?,
+                    }
+
+                    state = GatedState::Validating;
+                }
+                GatedState::Validating => {
+                    responder.update("gated: validating").await;
+
+                    match tools::RunTests::new(&test_command)
+                        .run_on(executor.as_ref())
+                        .await
+                    {
+                        Ok(()) => state = GatedState::Advancing,
+                        Err(failure) => {
+                            if attempt >= self.max_retries {
+                                anyhow::bail!(
+                                    "Gated mode exhausted {} attempts, last failure:\n{failure}",
+                                    self.max_retries
+                                );
+                            }
+
+                            tools::ResetFile::new(&agent_environment.start_ref)
+                                .reset_all(executor.as_ref())
+                                .await?;
+
+                            diagnostics = Some(failure.to_string());
+                            state = GatedState::Editing;
+                        }
+                    }
+                }
+                GatedState::Advancing => {
+                    responder.update("gated: advancing").await;
+                    agent_environment
+                        .commit_and_push(&session.repository, executor.as_ref())
+                        .await
+                        .context("Failed to commit and push gated changes")?;
+                    state = GatedState::Done;
+                }
+                GatedState::Done => {
+                    responder.update("gated: done, changes pushed").await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,519 @@
+//! A dependency-graph executor for plan-and-act mode.
+//!
+//! Instead of handing an entire task to a single delegated coding agent, the planning agent
+//! emits a set of named subtasks together with their prerequisites. [`PlanExecutor`] dispatches
+//! every subtask whose prerequisites are satisfied to its own coding agent, respecting a
+//! configurable max concurrency, and cascades a failure to its (not yet started) dependents
+//! while letting independent branches keep running.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result};
+use async_trait::async_trait;
+use derive_builder::Builder;
+use serde::Deserialize;
+use swiftide::{
+    chat_completion::{errors::ToolError, Tool, ToolOutput, ToolSpec},
+    traits::{AgentContext, ToolBox, ToolExecutor},
+};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+
+use super::{agents, git_agent_environment::GitAgentEnvironment, session::Session};
+
+pub type TaskName = String;
+
+/// A single subtask emitted by the planning agent
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub name: TaskName,
+    pub description: String,
+    pub prerequisites: Vec<TaskName>,
+}
+
+/// Outcome reported for a single task: once its coding agent finishes running, or as `Failed`
+/// when the task is cancelled or a prerequisite failed before it started
+#[derive(Debug, Clone)]
+pub enum ExecutionStatus {
+    Complete,
+    Failed(String),
+}
+
+/// A status update for a single named task, forwarded to the `Responder` so the UI can render
+/// per-task outcomes instead of a single linear hand-off message.
+#[derive(Debug, Clone)]
+pub struct ExecutionStatusMsg {
+    pub name: TaskName,
+    pub status: ExecutionStatus,
+}
+
+/// Per-task working context: the task's target description plus a scratch and an output
+/// directory under the git worktree. `run_task` builds one for every task but does not hand it
+/// to the coding agent, so tasks running in parallel all edit the same worktree.
+#[derive(Debug, Clone)]
+pub struct ExecutionContext {
+    pub target: String,
+    pub scratch_dir: PathBuf,
+    pub output_dir: PathBuf,
+}
+
+impl ExecutionContext {
+    fn for_task(task: &Task, agent_environment: &GitAgentEnvironment) -> Self {
+        let worktree_dir = agent_environment.worktree_path();
+        Self {
+            target: task.description.clone(),
+            scratch_dir: worktree_dir.join(".kwaak").join("scratch").join(&task.name),
+            output_dir: worktree_dir.join(".kwaak").join("out").join(&task.name),
+        }
+    }
+}
+
+const DEFAULT_MAX_CONCURRENCY: usize = 3;
+
+/// Dispatches a [`Task`] DAG to coding agents, respecting prerequisites and max concurrency
+pub struct PlanExecutor {
+    tasks: BTreeMap<TaskName, Task>,
+    max_concurrency: usize,
+}
+
+impl PlanExecutor {
+    #[must_use]
+    pub fn new(tasks: Vec<Task>) -> Self {
+        Self {
+            tasks: tasks
+                .into_iter()
+                .map(|task| (task.name.clone(), task))
+                .collect(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        }
+    }
+
+    #[must_use]
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Runs the task graph to completion, dispatching ready tasks as their prerequisites are
+    /// satisfied. A failed task's not-yet-started dependents are cascaded to `Failed` via plain
+    /// bookkeeping (their prerequisite never satisfies, so they're never dispatched).
+    /// `cancel_token` is for *external* cancellation of the whole plan: each dispatched task gets
+    /// a child of it, so cancelling it stops new dispatches and fails every pending task, while
+    /// any task already in flight observes the same cancellation through its child token.
+    /// Returns once every task has either completed or been failed/cancelled.
+    pub async fn run(
+        self,
+        session: &Arc<Session>,
+        executor: &Arc<dyn ToolExecutor>,
+        available_tools: &[Box<dyn Tool>],
+        tool_boxes: &[Box<dyn ToolBox>],
+        agent_environment: &GitAgentEnvironment,
+        cancel_token: &CancellationToken,
+        status_tx: &UnboundedSender<ExecutionStatusMsg>,
+    ) -> Result<()> {
+        let mut pending = self.tasks;
+        let mut satisfied: BTreeSet<TaskName> = BTreeSet::new();
+        let mut failed: BTreeSet<TaskName> = BTreeSet::new();
+        let mut join_set = tokio::task::JoinSet::new();
+
+        while !pending.is_empty() || !join_set.is_empty() {
+            if cancel_token.is_cancelled() {
+                for (name, _) in std::mem::take(&mut pending) {
+                    failed.insert(name.clone());
+                    let _ = status_tx.send(ExecutionStatusMsg {
+                        name,
+                        status: ExecutionStatus::Failed("cancelled".to_string()),
+                    });
+                }
+
+                if join_set.is_empty() {
+                    break;
+                }
+
+                // Tasks already in flight have a child of this token and will unwind via their
+                // own `cancel_token.cancelled()` branch in `run_task`; just drain them.
+                if let Some(joined) = join_set.join_next().await {
+                    joined.context("plan task panicked")?;
+                }
+                continue;
+            }
+
+            let available_capacity = self.max_concurrency.saturating_sub(join_set.len());
+            let (cascaded, dispatch) =
+                partition_ready(&pending, &satisfied, &failed, available_capacity);
+
+            // Tracks whether this round made any progress at all — either by dispatching a task
+            // or by cascading a failure to one whose prerequisite already failed. Only a round
+            // that does neither (while tasks remain and nothing is running) is a genuine stall.
+            let progressed = !cascaded.is_empty() || !dispatch.is_empty();
+
+            for name in cascaded {
+                let task = pending.remove(&name).expect("name came from pending");
+                failed.insert(task.name.clone());
+                let _ = status_tx.send(ExecutionStatusMsg {
+                    name: task.name,
+                    status: ExecutionStatus::Failed("cancelled: a prerequisite failed".to_string()),
+                });
+            }
+
+            for name in dispatch {
+                let task = pending.remove(&name).expect("name came from pending");
+                let task_token = cancel_token.child_token();
+                let task_name = name.clone();
+                let session = Arc::clone(session);
+                let executor = Arc::clone(executor);
+                let available_tools = available_tools.to_vec();
+                let tool_boxes = tool_boxes.to_vec();
+                let agent_environment = agent_environment.clone();
+                let status_tx = status_tx.clone();
+
+                join_set.spawn(async move {
+                    let result = run_task(
+                        task,
+                        &session,
+                        &executor,
+                        &available_tools,
+                        &tool_boxes,
+                        &agent_environment,
+                        &task_token,
+                        &status_tx,
+                    )
+                    .await;
+                    (task_name, result)
+                });
+            }
+
+            if join_set.is_empty() {
+                if pending.is_empty() {
+                    break;
+                }
+
+                if !progressed {
+                    anyhow::bail!(
+                        "plan has unresolvable task dependencies: {:?}",
+                        pending.keys().collect::<Vec<_>>()
+                    );
+                }
+                continue;
+            }
+
+            if let Some(joined) = join_set.join_next().await {
+                let (name, result) = joined.context("plan task panicked")?;
+                match result {
+                    Ok(()) => {
+                        satisfied.insert(name);
+                    }
+                    Err(_) => {
+                        failed.insert(name);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the names whose prerequisites are all settled (present in `satisfied` or `failed`) into
+/// those that cascade straight to `Failed` (at least one prerequisite failed) and those ready to
+/// dispatch, capped at `available_capacity`. Pure bookkeeping only — no IO, no task spawning — so
+/// [`PlanExecutor::run`]'s scheduling invariants can be exercised directly; see the `tests` module
+/// below.
+fn partition_ready(
+    pending: &BTreeMap<TaskName, Task>,
+    satisfied: &BTreeSet<TaskName>,
+    failed: &BTreeSet<TaskName>,
+    available_capacity: usize,
+) -> (Vec<TaskName>, Vec<TaskName>) {
+    let mut cascaded = Vec::new();
+    let mut dispatch = Vec::new();
+
+    for (name, task) in pending {
+        let ready = task
+            .prerequisites
+            .iter()
+            .all(|dep| satisfied.contains(dep) || failed.contains(dep));
+        if !ready {
+            continue;
+        }
+
+        if task.prerequisites.iter().any(|dep| failed.contains(dep)) {
+            cascaded.push(name.clone());
+        } else if dispatch.len() < available_capacity {
+            dispatch.push(name.clone());
+        }
+    }
+
+    (cascaded, dispatch)
+}
+
+/// The `plan_and_execute` tool: parses the planning agent's `tasks` argument into a [`Task`] DAG
+/// and drives it to completion with a [`PlanExecutor`], instead of leaving the parsed tasks with
+/// nowhere to run.
+#[derive(Clone, Builder)]
+#[builder(build_fn(private), setter(into))]
+pub struct PlanAndExecute {
+    session: Arc<Session>,
+    executor: Arc<dyn ToolExecutor>,
+    available_tools: Vec<Box<dyn Tool>>,
+    tool_boxes: Vec<Box<dyn ToolBox>>,
+    agent_environment: GitAgentEnvironment,
+    max_concurrency: usize,
+    cancel_token: CancellationToken,
+    status_tx: UnboundedSender<ExecutionStatusMsg>,
+    tool_spec: ToolSpec,
+}
+
+impl PlanAndExecute {
+    #[must_use]
+    pub fn builder() -> PlanAndExecuteBuilder {
+        PlanAndExecuteBuilder::default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTask {
+    name: TaskName,
+    description: String,
+    #[serde(default)]
+    prerequisites: Vec<TaskName>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlanAndExecuteArgs {
+    tasks: Vec<RawTask>,
+}
+
+#[async_trait]
+impl Tool for PlanAndExecute {
+    async fn invoke(
+        &self,
+        _agent_context: &dyn AgentContext,
+        raw_args: Option<&str>,
+    ) -> Result<ToolOutput, ToolError> {
+        let raw_args = raw_args
+            .context("plan_and_execute requires a `tasks` argument")
+            .map_err(ToolError::wrapped)?;
+
+        let args: PlanAndExecuteArgs = serde_json::from_str(raw_args)
+            .context("Failed to parse `tasks`")
+            .map_err(ToolError::wrapped)?;
+
+        let tasks = args
+            .tasks
+            .into_iter()
+            .map(|task| Task {
+                name: task.name,
+                description: task.description,
+                prerequisites: task.prerequisites,
+            })
+            .collect();
+
+        PlanExecutor::new(tasks)
+            .with_max_concurrency(self.max_concurrency)
+            .run(
+                &self.session,
+                &self.executor,
+                &self.available_tools,
+                &self.tool_boxes,
+                &self.agent_environment,
+                &self.cancel_token,
+                &self.status_tx,
+            )
+            .await
+            .map_err(ToolError::wrapped)?;
+
+        Ok(ToolOutput::Text("Plan executed".to_string()))
+    }
+
+    fn name(&self) -> Cow<'_, str> {
+        self.tool_spec.name.clone().into()
+    }
+
+    fn tool_spec(&self) -> ToolSpec {
+        self.tool_spec.clone()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_task(
+    task: Task,
+    session: &Arc<Session>,
+    executor: &Arc<dyn ToolExecutor>,
+    available_tools: &[Box<dyn Tool>],
+    tool_boxes: &[Box<dyn ToolBox>],
+    agent_environment: &GitAgentEnvironment,
+    cancel_token: &CancellationToken,
+    status_tx: &UnboundedSender<ExecutionStatusMsg>,
+) -> Result<(), String> {
+    let send_status = |status: ExecutionStatus| {
+        let _ = status_tx.send(ExecutionStatusMsg {
+            name: task.name.clone(),
+            status,
+        });
+    };
+
+    if cancel_token.is_cancelled() {
+        let reason = "cancelled before starting".to_string();
+        send_status(ExecutionStatus::Failed(reason.clone()));
+        return Err(reason);
+    }
+
+    // Built per task but not passed to the coding agent, which works in the shared worktree.
+    let _context = ExecutionContext::for_task(&task, agent_environment);
+
+    let coding_agent = agents::coding::start(
+        session,
+        executor,
+        available_tools,
+        tool_boxes,
+        agent_environment,
+        task.description.clone(),
+    )
+    .await
+    .map_err(|err| err.to_string())?;
+
+    let task_agent_id = super::session::AgentId::new_v4();
+    if let Err(err) = session.spawn_agent(task_agent_id, coding_agent.clone()) {
+        let reason = err.to_string();
+        send_status(ExecutionStatus::Failed(reason.clone()));
+        return Err(reason);
+    }
+
+    let result = tokio::select! {
+        result = coding_agent.run() => {
+            match result {
+                Ok(()) => {
+                    send_status(ExecutionStatus::Complete);
+                    Ok(())
+                }
+                Err(err) => {
+                    let reason = err.to_string();
+                    send_status(ExecutionStatus::Failed(reason.clone()));
+                    Err(reason)
+                }
+            }
+        }
+        () = cancel_token.cancelled() => {
+            let reason = "cancelled".to_string();
+            send_status(ExecutionStatus::Failed(reason.clone()));
+            Err(reason)
+        }
+    };
+
+    // The task is done either way; drop its agent from the registry so a long-running
+    // plan-and-act session doesn't accumulate one stale entry per completed subtask.
+    let _ = session.stop_agent(task_agent_id);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{partition_ready, BTreeMap, BTreeSet, Task, TaskName};
+
+    fn task(name: &str, prerequisites: &[&str]) -> Task {
+        Task {
+            name: name.to_string(),
+            description: String::new(),
+            prerequisites: prerequisites.iter().map(|s| (*s).to_string()).collect(),
+        }
+    }
+
+    fn pending(tasks: &[Task]) -> BTreeMap<TaskName, Task> {
+        tasks
+            .iter()
+            .cloned()
+            .map(|task| (task.name.clone(), task))
+            .collect()
+    }
+
+    fn names(tasks: &[&str]) -> BTreeSet<TaskName> {
+        tasks.iter().map(|s| (*s).to_string()).collect()
+    }
+
+    #[test]
+    fn diamond_dependency_only_root_is_ready() {
+        // A -> B, C -> D
+        let tasks = [
+            task("a", &[]),
+            task("b", &["a"]),
+            task("c", &["a"]),
+            task("d", &["b", "c"]),
+        ];
+        let pending = pending(&tasks);
+
+        let (cascaded, dispatch) =
+            partition_ready(&pending, &BTreeSet::new(), &BTreeSet::new(), 10);
+
+        assert!(cascaded.is_empty());
+        assert_eq!(dispatch, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn diamond_dependency_dispatches_both_branches_once_root_completes() {
+        let tasks = [task("b", &["a"]), task("c", &["a"]), task("d", &["b", "c"])];
+        let pending = pending(&tasks);
+        let satisfied = names(&["a"]);
+
+        let (cascaded, dispatch) = partition_ready(&pending, &satisfied, &BTreeSet::new(), 10);
+
+        assert!(cascaded.is_empty());
+        assert_eq!(dispatch, vec!["b".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn failed_prerequisite_cascades_to_dependent() {
+        let tasks = [task("b", &["a"]), task("c", &[])];
+        let pending = pending(&tasks);
+        let failed = names(&["a"]);
+
+        let (cascaded, dispatch) = partition_ready(&pending, &BTreeSet::new(), &failed, 10);
+
+        assert_eq!(cascaded, vec!["b".to_string()]);
+        assert_eq!(dispatch, vec!["c".to_string()]);
+    }
+
+    #[test]
+    fn cascading_failure_is_not_gated_by_capacity() {
+        let tasks = [task("b", &["a"])];
+        let pending = pending(&tasks);
+        let failed = names(&["a"]);
+
+        // Zero capacity would block a dispatch, but a cascade isn't a dispatch: it must still
+        // happen so the plan doesn't stall waiting on a task that will never become ready.
+        let (cascaded, dispatch) = partition_ready(&pending, &BTreeSet::new(), &failed, 0);
+
+        assert_eq!(cascaded, vec!["b".to_string()]);
+        assert!(dispatch.is_empty());
+    }
+
+    #[test]
+    fn dispatch_is_capped_at_available_capacity() {
+        let tasks = [task("a", &[]), task("b", &[]), task("c", &[])];
+        let pending = pending(&tasks);
+
+        let (cascaded, dispatch) = partition_ready(&pending, &BTreeSet::new(), &BTreeSet::new(), 2);
+
+        assert!(cascaded.is_empty());
+        assert_eq!(dispatch.len(), 2);
+    }
+
+    #[test]
+    fn unresolved_cycle_yields_neither_cascade_nor_dispatch() {
+        // A cycle (a <-> b) never becomes ready: this is what `run` recognizes as a stall.
+        let tasks = [task("a", &["b"]), task("b", &["a"])];
+        let pending = pending(&tasks);
+
+        let (cascaded, dispatch) =
+            partition_ready(&pending, &BTreeSet::new(), &BTreeSet::new(), 10);
+
+        assert!(cascaded.is_empty());
+        assert!(dispatch.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     future::Future,
     pin::Pin,
     sync::{Arc, Mutex},
@@ -7,12 +8,12 @@ use std::{
 use anyhow::{Context as _, Result};
 use derive_builder::Builder;
 use rmcp::{
-    ServiceExt as _,
     model::{ClientInfo, Implementation},
-    transport::TokioChildProcess,
+    transport::{SseClientTransport, StreamableHttpClientTransport, TokioChildProcess},
+    ServiceExt as _,
 };
 use swiftide::{
-    agents::{AgentBuilder, tools::mcp::McpToolbox},
+    agents::{tools::mcp::McpToolbox, AgentBuilder},
     chat_completion::{ParamSpec, Tool, ToolSpec},
     traits::{SimplePrompt, ToolBox, ToolExecutor},
 };
@@ -22,15 +23,16 @@ use tokio_util::{sync::CancellationToken, task::AbortOnDropHandle};
 use uuid::Uuid;
 
 use crate::{
-    agent::{tools::DelegateAgent, util},
+    agent::util,
     commands::Responder,
-    config::{self, AgentEditMode, mcp::McpServer},
+    config::{self, mcp::McpServer, AgentEditMode},
     indexing::Index,
     repository::Repository,
 };
 
 use super::{
-    agents, git_agent_environment::GitAgentEnvironment, running_agent::RunningAgent, tools,
+    agents, gated, git_agent_environment::GitAgentEnvironment, plan, running_agent::RunningAgent,
+    session_config::SessionConfig, tools,
 };
 
 pub type OnAgentBuildFn = Arc<
@@ -39,16 +41,39 @@ pub type OnAgentBuildFn = Arc<
         + Sync,
 >;
 
+/// Identifies a single agent inside a session's registry
+pub type AgentId = Uuid;
+
+/// A generation counter snapshotted at the moment a `Route`/`Stop` message is minted for an
+/// agent.
+///
+/// `running_message_handler` compares the token carried by the message against the agent's
+/// *current* generation (bumped whenever the agent is swapped or stopped) before acting on it.
+/// If they don't match, the agent has since moved on and the message is stale, so it's dropped
+/// instead of resuming or tearing down an agent the sender no longer actually holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageToken(u64);
+
+/// Where a session currently is in its lifecycle, emitted on every transition so front-ends can
+/// render it instead of parsing the prose the default responder used to receive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionState {
+    Starting,
+    Indexing,
+    SettingUpGit,
+    StartingTools,
+    Running,
+    Stopping,
+    Stopped,
+    Failed(String),
+}
+
 /// Session represents the abstract state of an ongoing agent interaction (i.e. in a chat)
 ///
 /// Consider the implementation 'emergent architecture' (an excuse for an isolated mess)
 ///
 /// NOTE: Seriously though, this file is a mess on purpose so we can figure out the best way to
 /// to architect this.
-///
-/// Some future ideas:
-///     - Session configuration from a file
-///     - A registry pattern for agents, so you could in theory run multiple concurrent
 #[derive(Clone, Builder)]
 #[builder(build_fn(private), setter(into))]
 pub struct Session {
@@ -62,18 +87,46 @@ pub struct Session {
 
     /// Handle to send messages to the running session
     running_session_tx: UnboundedSender<SessionMessage>,
+
+    /// Per-agent generation counters, bumped whenever an agent slot is replaced or stopped.
+    /// Shared with the `RunningSession` so messages minted here can be checked for staleness
+    /// over there.
+    #[builder(default)]
+    agent_generations: Arc<Mutex<HashMap<AgentId, u64>>>,
 }
 
-/// Messages that can be send from i.e. a tool to an active session
+/// Messages that can be sent from i.e. a tool to the session's running agent registry
 #[derive(Clone)]
 pub enum SessionMessage {
-    SwapAgent(RunningAgent),
+    /// Registers a newly started agent under `id`
+    Spawn { id: AgentId, agent: RunningAgent },
+    /// Routes a query to the agent at `id`, dropped if `token` is stale
+    Route {
+        id: AgentId,
+        query: String,
+        token: MessageToken,
+    },
+    /// Stops the agent at `id`, dropped if `token` is stale
+    Stop { id: AgentId, token: MessageToken },
+    /// Replaces the agent at `id` with a new one
+    SwapAgent { id: AgentId, agent: RunningAgent },
 }
 
 impl std::fmt::Debug for SessionMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::SwapAgent(_) => f.debug_tuple("SwapAgent").finish(),
+            Self::Spawn { id, .. } => f.debug_struct("Spawn").field("id", id).finish(),
+            Self::Route { id, token, .. } => f
+                .debug_struct("Route")
+                .field("id", id)
+                .field("token", token)
+                .finish(),
+            Self::Stop { id, token } => f
+                .debug_struct("Stop")
+                .field("id", id)
+                .field("token", token)
+                .finish(),
+            Self::SwapAgent { id, .. } => f.debug_struct("SwapAgent").field("id", id).finish(),
         }
     }
 }
@@ -84,20 +137,121 @@ impl Session {
         SessionBuilder::default()
     }
 
-    /// Inform the running session that the agent has been swapped
-    pub fn swap_agent(&self, agent: RunningAgent) -> Result<()> {
+    /// Registers a newly spawned agent with the running session's registry
+    pub fn spawn_agent(&self, id: AgentId, agent: RunningAgent) -> Result<()> {
+        self.agent_generations
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_insert(0);
+        self.running_session_tx
+            .send(SessionMessage::Spawn { id, agent })
+            .map_err(Into::into)
+    }
+
+    /// Inform the running session that the agent at `id` has been swapped
+    pub fn swap_agent(&self, id: AgentId, agent: RunningAgent) -> Result<()> {
+        self.bump_generation(id);
+        self.running_session_tx
+            .send(SessionMessage::SwapAgent { id, agent })
+            .map_err(Into::into)
+    }
+
+    /// Route a query to the agent at `id`, tagged with its current token so the running session
+    /// can silently drop it if the agent has since been swapped or stopped
+    pub fn route_agent(&self, id: AgentId, query: impl Into<String>) -> Result<()> {
+        let token = self.current_token(id);
         self.running_session_tx
-            .send(SessionMessage::SwapAgent(agent))
+            .send(SessionMessage::Route {
+                id,
+                query: query.into(),
+                token,
+            })
             .map_err(Into::into)
     }
+
+    /// Stop the agent at `id`, tagged with its current token
+    pub fn stop_agent(&self, id: AgentId) -> Result<()> {
+        let token = self.current_token(id);
+        self.running_session_tx
+            .send(SessionMessage::Stop { id, token })
+            .map_err(Into::into)
+    }
+
+    /// The token an agent is currently at. `route_agent` and `stop_agent` snapshot it when they
+    /// send their message, so a `Route`/`Stop` still in flight when the agent is swapped or
+    /// stopped is recognized as stale by the time the running session handles it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the generations mutex is poisoned
+    #[must_use]
+    pub fn current_token(&self, id: AgentId) -> MessageToken {
+        MessageToken(
+            *self
+                .agent_generations
+                .lock()
+                .unwrap()
+                .get(&id)
+                .unwrap_or(&0),
+        )
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the generations mutex is poisoned
+    fn bump_generation(&self, id: AgentId) -> MessageToken {
+        let mut generations = self.agent_generations.lock().unwrap();
+        let generation = generations.entry(id).or_insert(0);
+        *generation += 1;
+        MessageToken(*generation)
+    }
 }
 
 impl SessionBuilder {
-    /// Starts a session
+    /// Starts a session in the background, handing back a [`SessionState`] receiver immediately
+    /// so transitions can be observed from the moment startup begins. It is a `watch` receiver:
+    /// it holds only the latest state, so a reader that falls behind sees the most recent
+    /// transition rather than each one in turn.
+    ///
+    /// Previously the receiver only became reachable via [`RunningSession::subscribe_state`],
+    /// which doesn't exist until `start()` has already returned — so `Indexing`, `SettingUpGit`,
+    /// `StartingTools`, and even `Running` had always already fired before a caller could
+    /// subscribe, and `Failed` was unobservable entirely (no `RunningSession` is ever produced on
+    /// the error path). Consuming `self` and running startup on a spawned task lets the receiver
+    /// exist up front, with every transition including `Failed` sent to it as it happens.
     #[tracing::instrument(skip_all)]
-    pub async fn start(
+    pub fn start(
+        mut self,
+        index: impl Index + 'static + Clone + Send,
+    ) -> (
+        tokio::sync::watch::Receiver<SessionState>,
+        tokio::task::JoinHandle<Result<RunningSession>>,
+    ) {
+        let (state_tx, state_rx) = tokio::sync::watch::channel(SessionState::Starting);
+        let state_tx = Arc::new(state_tx);
+
+        let handle = tokio::spawn(async move {
+            match self.start_inner(&index, &state_tx).await {
+                Ok(running_session) => Ok(running_session),
+                Err(err) => {
+                    // Don't send stop on startup failure: no agent was ever started, so there's
+                    // nothing to tear down beyond reporting where we got to. This is also the one
+                    // transition with no `RunningSession` to subscribe through, so the receiver
+                    // handed back by `start` above is the only way a caller ever observes it.
+                    let _ = state_tx.send(SessionState::Failed(err.to_string()));
+                    Err(err)
+                }
+            }
+        });
+
+        (state_rx, handle)
+    }
+
+    async fn start_inner(
         &mut self,
         index: &(impl Index + 'static + Clone),
+        state_tx: &Arc<tokio::sync::watch::Sender<SessionState>>,
     ) -> Result<RunningSession> {
         let (running_session_tx, running_session_rx) = tokio::sync::mpsc::unbounded_channel();
 
@@ -112,6 +266,11 @@ impl SessionBuilder {
             .update("starting up agent for the first time, this might take a while")
             .await;
 
+        // Session-level config (`.kwaak/session.toml`, if present) is consulted first, falling
+        // through to the repository's global config for anything it doesn't set.
+        let session_config = SessionConfig::load(session.repository.path())
+            .context("Failed to load session config")?;
+
         let backoff = session.repository.config().backoff;
         let fast_query_provider: Box<dyn SimplePrompt> = session
             .repository
@@ -119,6 +278,8 @@ impl SessionBuilder {
             .indexing_provider()
             .get_simple_prompt_model(backoff)?;
 
+        state_tx.send(SessionState::Indexing).ok();
+
         let ((), executor, branch_name, initial_context) = tokio::try_join!(
             util::rename_chat(
                 &session.initial_query,
@@ -138,35 +299,45 @@ impl SessionBuilder {
             generate_initial_context(&session.repository, &session.initial_query, index)
         )?;
 
+        // A session config initial-context override replaces whatever was just retrieved from
+        // the index, rather than merging with it.
+        let initial_context = session_config
+            .initial_context
+            .clone()
+            .unwrap_or(initial_context);
+
+        state_tx.send(SessionState::SettingUpGit).ok();
+
         let git_environment =
             GitAgentEnvironment::setup(&session.repository, &executor, &branch_name).await?;
 
-        let builtin_tools =
-            available_builtin_tools(&session.repository, Some(&git_environment), index)?;
+        state_tx.send(SessionState::StartingTools).ok();
+
+        let builtin_tools = available_builtin_tools(
+            &session.repository,
+            Some(&git_environment),
+            index,
+            &session_config,
+        )?;
 
-        let mcp_toolboxes = start_mcp_toolboxes(&session.repository).await?;
+        let mcp_toolboxes = start_mcp_toolboxes(&session.repository, &session_config).await?;
 
         let mcp_dyn = mcp_toolboxes
             .iter()
             .map(|toolbox| Box::new(toolbox.clone()) as Box<dyn ToolBox>)
             .collect::<Vec<_>>();
 
-        let mut builder = match session.repository.config().agent {
-            config::SupportedAgentConfigurations::Coding => {
-                agents::coding::build(
-                    &session.repository,
-                    &session.default_responder,
-                    &executor,
-                    &builtin_tools,
-                    &mcp_dyn,
-                    &git_environment,
-                    Some(&initial_context),
-                )
-                .await
-            }
-            // TODO: Strip tools for delegate agent and add tool for delegate
-            config::SupportedAgentConfigurations::PlanAct => {
-                build_plan_and_act(
+        let agent_configuration = session_config
+            .agent
+            .unwrap_or(session.repository.config().agent);
+
+        // `Gated` already has a running agent by the time `build_gated` returns (the background
+        // gated loop needs it live), so it has no `AgentBuilder` left to hand `on_agent_build` or
+        // feed through the generic `builder.build()` step below; it registers its id/agent pair
+        // as the primary agent directly instead.
+        let (primary_agent_id, active_agent) = match agent_configuration {
+            config::SupportedAgentConfigurations::Gated => {
+                build_gated(
                     &session,
                     &executor,
                     &builtin_tools,
@@ -174,24 +345,60 @@ impl SessionBuilder {
                     &git_environment,
                     &initial_context,
                 )
-                .await
+                .await?
             }
-        }?;
+            other => {
+                let mut builder = match other {
+                    config::SupportedAgentConfigurations::Coding => {
+                        agents::coding::build(
+                            &session.repository,
+                            &session.default_responder,
+                            &executor,
+                            &builtin_tools,
+                            &mcp_dyn,
+                            &git_environment,
+                            Some(&initial_context),
+                        )
+                        .await
+                    }
+                    // TODO: Strip tools for delegate agent and add tool for delegate
+                    config::SupportedAgentConfigurations::PlanAct => {
+                        build_plan_and_act(
+                            &session,
+                            &executor,
+                            &builtin_tools,
+                            &mcp_dyn,
+                            &git_environment,
+                            &initial_context,
+                        )
+                        .await
+                    }
+                    config::SupportedAgentConfigurations::Gated => unreachable!(
+                        "Gated is handled above before the generic builder path is reached"
+                    ),
+                }?;
 
-        if let Some(Some(on_agent_build)) = self.on_agent_build.take() {
-            on_agent_build(&mut builder).await?;
-        }
+                if let Some(Some(on_agent_build)) = self.on_agent_build.take() {
+                    on_agent_build(&mut builder).await?;
+                }
+
+                (AgentId::new_v4(), builder.build()?.into())
+            }
+        };
 
-        let active_agent = builder.build()?.into();
+        let mut active_agents = HashMap::new();
+        active_agents.insert(primary_agent_id, Arc::new(Mutex::new(active_agent)));
 
         let mut running_session = RunningSession {
-            active_agent: Arc::new(Mutex::new(active_agent)),
+            active_agents: Arc::new(Mutex::new(active_agents)),
+            primary_agent_id,
             session,
             executor,
             git_environment,
             cancel_token: Arc::new(Mutex::new(CancellationToken::new())),
             message_task_handle: None,
             mcp_toolboxes,
+            state_tx: Arc::clone(state_tx),
         };
 
         // TODO: Consider how this might be dropped
@@ -202,11 +409,13 @@ impl SessionBuilder {
 
         running_session.message_task_handle = Some(Arc::new(AbortOnDropHandle::new(handle)));
 
+        state_tx.send(SessionState::Running).ok();
+
         Ok(running_session)
     }
 }
 
-/// Spawns a small task to handle messages sent to the active session
+/// Spawns a small task that dispatches messages sent to the session's agent registry
 async fn running_message_handler(
     running_session: RunningSession,
     mut running_session_rx: tokio::sync::mpsc::UnboundedReceiver<SessionMessage>,
@@ -214,8 +423,27 @@ async fn running_message_handler(
     while let Some(message) = running_session_rx.recv().await {
         tracing::debug!(?message, "Session received message");
         match message {
-            SessionMessage::SwapAgent(agent) => {
-                running_session.swap_agent(agent);
+            SessionMessage::Spawn { id, agent } => {
+                running_session.register_agent(id, agent);
+            }
+            SessionMessage::Route { id, query, token } => {
+                if !running_session.is_current(id, token) {
+                    tracing::debug!(?id, "Dropping stale route message for agent");
+                    continue;
+                }
+                if let Err(err) = running_session.query_agent(id, &query).await {
+                    tracing::error!(?err, ?id, "Failed to route query to agent");
+                }
+            }
+            SessionMessage::Stop { id, token } => {
+                if !running_session.is_current(id, token) {
+                    tracing::debug!(?id, "Dropping stale stop message for agent");
+                    continue;
+                }
+                running_session.stop(id).await;
+            }
+            SessionMessage::SwapAgent { id, agent } => {
+                running_session.swap_agent(id, agent);
             }
         }
     }
@@ -229,6 +457,10 @@ static BLACKLIST_DELEGATE_TOOLS: &[&str] = &[
     "add_lines",
 ];
 
+/// Builds the plan-and-act agent: rather than handing the whole task to a single delegated
+/// coding agent, the agent plans a dependency graph of named subtasks and the `plan_and_execute`
+/// tool dispatches each one to its own coding agent as soon as its prerequisites are satisfied,
+/// bounded by a configurable max concurrency.
 async fn build_plan_and_act(
     session: &Arc<Session>,
     executor: &Arc<dyn ToolExecutor>,
@@ -237,52 +469,133 @@ async fn build_plan_and_act(
     agent_environment: &GitAgentEnvironment,
     initial_context: &str,
 ) -> Result<AgentBuilder> {
-    let coding_agent = agents::coding::start(
-        &session,
-        &executor,
-        &available_tools,
-        &tool_boxes,
-        &agent_environment,
-        String::new(),
-    )
-    .await?;
-
-    let delegate_tool = DelegateAgent::builder()
-        .session(Arc::clone(&session))
-        .agent(coding_agent)
+    let (status_tx, status_rx) = tokio::sync::mpsc::unbounded_channel();
+
+    tokio::spawn(forward_execution_status(
+        status_rx,
+        Arc::clone(&session.default_responder),
+    ));
+
+    let plan_tool = plan::PlanAndExecute::builder()
+        .session(Arc::clone(session))
+        .executor(Arc::clone(executor))
+        .available_tools(available_tools.to_vec())
+        .tool_boxes(tool_boxes.to_vec())
+        .agent_environment(agent_environment.clone())
+        .max_concurrency(session.repository.config().plan_max_concurrency())
+        .cancel_token(CancellationToken::new())
+        .status_tx(status_tx)
         .tool_spec(
             ToolSpec::builder()
-                .name("delegate_coding_agent")
-                .description("If you have a coding task, delegate to the coding agent. Provide a thorough description of the task and relevant details.")
+                .name("plan_and_execute")
+                .description(
+                    "Break the task into a dependency graph of named subtasks and run them. \
+                     Each subtask is dispatched to its own coding agent as soon as its \
+                     prerequisites complete, so independent subtasks run in parallel.",
+                )
                 .parameters(vec![ParamSpec::builder()
-                    .name("task")
-                    .description("An in depth description of the task")
+                    .name("tasks")
+                    .description(
+                        "A JSON array of { name, description, prerequisites } objects, where \
+                         `prerequisites` lists the names of subtasks that must complete first",
+                    )
                     .build()?])
                 .build()?,
         )
         .build()
-        .context("Failed to build delegate tool")?;
+        .context("Failed to build plan-and-execute tool")?;
 
-    // Blacklist tools from the list then add the delegate tool
-    let delegate_tools = available_tools
+    // Blacklist tools from the list then add the plan-and-execute tool
+    let plan_tools = available_tools
         .iter()
         .filter(|tool| !BLACKLIST_DELEGATE_TOOLS.contains(&tool.name().as_ref()))
         .cloned()
-        .chain(std::iter::once(delegate_tool.boxed()))
+        .chain(std::iter::once(plan_tool.boxed()))
         .collect::<Vec<_>>();
 
     agents::delegate::build(
         &session.repository,
         &session.default_responder,
-        &executor,
-        &delegate_tools,
-        &tool_boxes,
-        &agent_environment,
+        executor,
+        &plan_tools,
+        tool_boxes,
+        agent_environment,
         Some(initial_context),
     )
     .await
 }
 
+/// Forwards per-task completion/failure status to the session's responder so the UI can render
+/// per-task outcomes instead of a single linear hand-off message.
+async fn forward_execution_status(
+    mut status_rx: tokio::sync::mpsc::UnboundedReceiver<plan::ExecutionStatusMsg>,
+    responder: Arc<dyn Responder>,
+) {
+    while let Some(msg) = status_rx.recv().await {
+        let line = match msg.status {
+            plan::ExecutionStatus::Complete => format!("[{}] complete", msg.name),
+            plan::ExecutionStatus::Failed(reason) => format!("[{}] failed: {reason}", msg.name),
+        };
+        responder.update(&line).await;
+    }
+}
+
+/// Builds the gated agent: a coding agent driven autonomously through `gated::GatedRunner`'s
+/// `Editing -> Validating -> Advancing -> Done` loop, so the user gets a hands-off "keep going
+/// until the tests are green, then commit" mode instead of approving each turn.
+///
+/// Returns the id the agent is registered under together with the agent itself, rather than an
+/// `AgentBuilder`: the agent has to already be running before the background gated loop can drive
+/// it, so unlike `Coding`/`PlanAct` there's no separate builder step left to do. The caller
+/// registers this same `(id, agent)` pair as the session's primary agent, so a user querying "the"
+/// session agent and the gated loop are always looking at the same conversation.
+async fn build_gated(
+    session: &Arc<Session>,
+    executor: &Arc<dyn ToolExecutor>,
+    available_tools: &[Box<dyn Tool>],
+    tool_boxes: &[Box<dyn ToolBox>],
+    agent_environment: &GitAgentEnvironment,
+    initial_context: &str,
+) -> Result<(AgentId, RunningAgent)> {
+    let coding_agent = agents::coding::start(
+        session,
+        executor,
+        available_tools,
+        tool_boxes,
+        agent_environment,
+        initial_context.to_string(),
+    )
+    .await?;
+
+    let gated_agent_id = AgentId::new_v4();
+
+    let runner = gated::GatedRunner::new(session.repository.config().gated_max_retries());
+
+    let runner_session = Arc::clone(session);
+    let responder = Arc::clone(&session.default_responder);
+    let runner_executor = Arc::clone(executor);
+    let runner_agent_environment = agent_environment.clone();
+    let runner_agent = coding_agent.clone();
+
+    tokio::spawn(async move {
+        if let Err(err) = runner
+            .run(
+                &runner_session,
+                &responder,
+                &runner_executor,
+                &runner_agent_environment,
+                &runner_agent,
+            )
+            .await
+        {
+            tracing::error!(?err, "Gated run failed");
+            responder.update(&format!("gated mode failed: {err}")).await;
+        }
+    });
+
+    Ok((gated_agent_id, coding_agent))
+}
+
 /// References a running session
 /// Meant to be cloned
 // TODO: Merge with session?
@@ -290,7 +603,11 @@ async fn build_plan_and_act(
 #[allow(dead_code)]
 pub struct RunningSession {
     session: Arc<Session>,
-    active_agent: Arc<Mutex<RunningAgent>>,
+    active_agents: Arc<Mutex<HashMap<AgentId, Arc<Mutex<RunningAgent>>>>>,
+    /// The id of the agent started when the session itself was built (the top-level coding or
+    /// plan-and-act/delegate agent), kept around so callers that only care about "the" agent
+    /// (e.g. the chat UI) don't need to track ids themselves.
+    primary_agent_id: AgentId,
     message_task_handle: Option<Arc<AbortOnDropHandle<()>>>,
     mcp_toolboxes: Vec<McpToolbox>,
 
@@ -298,10 +615,16 @@ pub struct RunningSession {
     git_environment: GitAgentEnvironment,
 
     cancel_token: Arc<Mutex<CancellationToken>>,
+
+    /// Broadcasts [`SessionState`] transitions; subscribe via [`RunningSession::subscribe_state`]
+    state_tx: Arc<tokio::sync::watch::Sender<SessionState>>,
 }
 
 impl Drop for RunningSession {
     fn drop(&mut self) {
+        // A `RunningSession` only ever exists once every startup step has succeeded, so
+        // `mcp_toolboxes` here only ever holds toolboxes that actually started — nothing more to
+        // reconcile, just cancel them all.
         tokio::task::block_in_place(|| {
             tokio::runtime::Handle::current().block_on(async {
                 for mcp in &mut self.mcp_toolboxes {
@@ -315,34 +638,97 @@ impl Drop for RunningSession {
 }
 
 impl RunningSession {
-    /// Get a cheap copy of the active agent
+    /// The id of the session's primary (top-level) agent
+    #[must_use]
+    pub fn primary_agent_id(&self) -> AgentId {
+        self.primary_agent_id
+    }
+
+    /// Subscribe to [`SessionState`] transitions
+    #[must_use]
+    pub fn subscribe_state(&self) -> tokio::sync::watch::Receiver<SessionState> {
+        self.state_tx.subscribe()
+    }
+
+    /// Stops every registered agent and tears the session down, emitting `Stopping`/`Stopped`
+    /// state transitions as it goes.
     ///
     /// # Panics
     ///
-    /// Panics if the agent mutex is poisoned
+    /// Panics if the registry mutex is poisoned
+    pub async fn shutdown(&self) {
+        self.state_tx.send(SessionState::Stopping).ok();
+
+        let ids = self
+            .active_agents
+            .lock()
+            .unwrap()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            self.stop(id).await;
+        }
+
+        self.state_tx.send(SessionState::Stopped).ok();
+    }
+
+    /// Get a cheap copy of the agent at `id`, if it's registered
+    ///
+    /// # Panics
+    ///
+    /// Panics if the registry or agent mutex is poisoned
     #[must_use]
-    pub fn active_agent(&self) -> RunningAgent {
-        self.active_agent.lock().unwrap().clone()
+    pub fn active_agent(&self, id: AgentId) -> Option<RunningAgent> {
+        self.active_agents
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|agent| agent.lock().unwrap().clone())
     }
 
-    /// Run an agent with a query
-    pub async fn query_agent(&self, query: &str) -> Result<()> {
-        self.active_agent().query(query).await
+    /// Run the agent at `id` with a query
+    pub async fn query_agent(&self, id: AgentId, query: &str) -> Result<()> {
+        let Some(agent) = self.active_agent(id) else {
+            anyhow::bail!("No agent registered for {id}");
+        };
+        agent.query(query).await
     }
 
-    /// Run an agent without a query
-    pub async fn run_agent(&self) -> Result<()> {
-        self.active_agent().run().await
+    /// Run the agent at `id` without a query
+    pub async fn run_agent(&self, id: AgentId) -> Result<()> {
+        let Some(agent) = self.active_agent(id) else {
+            anyhow::bail!("No agent registered for {id}");
+        };
+        agent.run().await
     }
 
-    /// Swap the current active agent with a new one
+    /// Registers a newly spawned agent under `id`
     ///
     /// # Panics
     ///
-    /// Panics if the agent mutex is poisoned
-    pub fn swap_agent(&self, running_agent: RunningAgent) {
-        let mut lock = self.active_agent.lock().unwrap();
-        *lock = running_agent;
+    /// Panics if the registry mutex is poisoned
+    pub fn register_agent(&self, id: AgentId, agent: RunningAgent) {
+        self.active_agents
+            .lock()
+            .unwrap()
+            .insert(id, Arc::new(Mutex::new(agent)));
+    }
+
+    /// Swap the agent at `id` with a new one, registering it if it wasn't already present
+    ///
+    /// # Panics
+    ///
+    /// Panics if the registry mutex is poisoned
+    pub fn swap_agent(&self, id: AgentId, running_agent: RunningAgent) {
+        let mut agents = self.active_agents.lock().unwrap();
+        match agents.get(&id) {
+            Some(slot) => *slot.lock().unwrap() = running_agent,
+            None => {
+                agents.insert(id, Arc::new(Mutex::new(running_agent)));
+            }
+        }
     }
 
     #[must_use]
@@ -375,16 +761,24 @@ impl RunningSession {
         *lock = CancellationToken::new();
     }
 
-    /// Stops the active agent
+    /// Stops the agent at `id`, removes it from the registry, and bumps its generation so any
+    /// `Route`/`Stop` message already in flight for it (minted before this stop landed) is
+    /// recognized as stale instead of being acted on afterwards.
     ///
     /// # Panics
     ///
-    /// Panics if the agent mutex is poisoned
-    pub async fn stop(&self) {
-        // When sessions have multiple agents, they should be stopped here
+    /// Panics if the registry mutex is poisoned
+    pub async fn stop(&self, id: AgentId) {
         self.reset_cancel_token();
-        let lock = self.active_agent.lock().unwrap().clone();
-        lock.stop().await;
+        let agent = self.active_agents.lock().unwrap().remove(&id);
+        self.session.bump_generation(id);
+        if let Some(agent) = agent {
+            agent.lock().unwrap().clone().stop().await;
+        }
+    }
+
+    fn is_current(&self, id: AgentId, token: MessageToken) -> bool {
+        token == self.session.current_token(id)
     }
 }
 
@@ -403,6 +797,7 @@ pub fn available_builtin_tools(
     repository: &Arc<Repository>,
     agent_env: Option<&GitAgentEnvironment>,
     index: &(impl Index + 'static + Clone),
+    session_config: &SessionConfig,
 ) -> Result<Vec<Box<dyn Tool>>> {
     let index = index.clone();
     let mut tools = vec![
@@ -415,8 +810,12 @@ pub fn available_builtin_tools(
         Box::new(tools::ExplainCode::new(index, Arc::clone(&repository))),
     ];
 
+    let agent_edit_mode = session_config
+        .agent_edit_mode
+        .unwrap_or(repository.config().agent_edit_mode);
+
     // agent edit mode specific tools
-    match repository.config().agent_edit_mode {
+    match agent_edit_mode {
         AgentEditMode::Whole => {
             tools.push(tools::write_file());
             tools.push(tools::read_file());
@@ -459,63 +858,173 @@ pub fn available_builtin_tools(
     }
 
     tools.retain(|tool| {
-        !repository
-            .config()
-            .disabled_tools()
-            .iter()
-            .any(|s| *s == tool.name().as_ref())
+        let name = tool.name();
+        session_config.allows_tool(name.as_ref())
+            && !repository
+                .config()
+                .disabled_tools()
+                .iter()
+                .any(|s| *s == name.as_ref())
     });
 
     Ok(tools)
 }
 
-pub async fn start_mcp_toolboxes(repository: &Repository) -> Result<Vec<McpToolbox>> {
+/// Starts every configured MCP toolbox. A session config that declares its own `mcp` list takes
+/// over entirely rather than merging with the repository's global list.
+///
+/// If a service fails to start partway through, every toolbox already started in this call is
+/// cancelled before the error is returned — a `RunningSession` doesn't exist yet at this point,
+/// so nothing else will ever get a chance to clean these up.
+pub async fn start_mcp_toolboxes(
+    repository: &Repository,
+    session_config: &SessionConfig,
+) -> Result<Vec<McpToolbox>> {
     let mut services = Vec::new();
-    if let Some(mcp_services) = &repository.config().mcp {
+
+    let mcp_services = session_config
+        .mcp
+        .as_ref()
+        .or(repository.config().mcp.as_ref());
+
+    if let Some(mcp_services) = mcp_services {
         for service in mcp_services {
-            match service {
-                McpServer::SubProcess {
-                    name,
-                    command,
-                    args,
-                    filter,
-                    env,
-                } => {
-                    if command.is_empty() {
-                        anyhow::bail!("Empty command for mcp tool");
-                    }
-                    let client_info = ClientInfo {
-                        client_info: Implementation {
-                            name: "kwaak".into(),
-                            version: env!("CARGO_PKG_VERSION").into(),
-                        },
-                        ..Default::default()
-                    };
-
-                    let mut cmd = tokio::process::Command::new(command);
-
-                    cmd.args(args);
-
-                    if let Some(env) = env {
-                        for (key, value) in env {
-                            cmd.env(key, value.expose_secret());
-                        }
+            if let Err(err) = start_one_mcp_toolbox(service, &mut services).await {
+                for mut toolbox in services {
+                    if let Err(cancel_err) = toolbox.cancel().await {
+                        tracing::error!(
+                            ?cancel_err,
+                            "Failed to cancel mcp service after a sibling failed to start"
+                        );
                     }
+                }
+                return Err(err);
+            }
+        }
+    }
 
-                    let service = client_info.serve(TokioChildProcess::new(&mut cmd)?).await?;
+    Ok(services)
+}
 
-                    let mut toolbox = McpToolbox::from_running_service(service)
-                        .with_name(name)
-                        .to_owned();
+fn kwaak_client_info() -> ClientInfo {
+    ClientInfo {
+        client_info: Implementation {
+            name: "kwaak".into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+        },
+        ..Default::default()
+    }
+}
 
-                    if let Some(filter) = filter {
-                        toolbox.with_filter(filter.clone());
-                    }
+/// Expands secret-typed header values the same way the subprocess transport already expands
+/// `env` via `expose_secret()`. The values are read from the config as they are written there;
+/// they stay redacted in `Debug` output until they are put into the header map.
+fn expand_headers(
+    headers: Option<&std::collections::HashMap<String, config::Secret>>,
+) -> Result<reqwest::header::HeaderMap> {
+    let mut map = reqwest::header::HeaderMap::new();
+
+    for (key, value) in headers.into_iter().flatten() {
+        let name = reqwest::header::HeaderName::from_bytes(key.as_bytes())
+            .with_context(|| format!("Invalid MCP header name `{key}`"))?;
+        let value = reqwest::header::HeaderValue::from_str(value.expose_secret())
+            .with_context(|| format!("Invalid MCP header value for `{key}`"))?;
+        map.insert(name, value);
+    }
+
+    Ok(map)
+}
+
+async fn start_one_mcp_toolbox(service: &McpServer, services: &mut Vec<McpToolbox>) -> Result<()> {
+    match service {
+        McpServer::SubProcess {
+            name,
+            command,
+            args,
+            filter,
+            env,
+        } => {
+            if command.is_empty() {
+                anyhow::bail!("Empty command for mcp tool");
+            }
 
-                    services.push(toolbox);
+            let mut cmd = tokio::process::Command::new(command);
+
+            cmd.args(args);
+
+            if let Some(env) = env {
+                for (key, value) in env {
+                    cmd.env(key, value.expose_secret());
                 }
             }
+
+            let service = kwaak_client_info()
+                .serve(TokioChildProcess::new(&mut cmd)?)
+                .await?;
+
+            let mut toolbox = McpToolbox::from_running_service(service)
+                .with_name(name)
+                .to_owned();
+
+            if let Some(filter) = filter {
+                toolbox.with_filter(filter.clone());
+            }
+
+            services.push(toolbox);
+        }
+        McpServer::Sse {
+            name,
+            url,
+            headers,
+            filter,
+        } => {
+            let client = reqwest::Client::builder()
+                .default_headers(expand_headers(headers.as_ref())?)
+                .build()
+                .context("Failed to build HTTP client for SSE MCP transport")?;
+
+            let transport = SseClientTransport::start_with_client(client, url.clone())
+                .await
+                .with_context(|| format!("Failed to start SSE MCP transport at {url}"))?;
+
+            let service = kwaak_client_info().serve(transport).await?;
+
+            let mut toolbox = McpToolbox::from_running_service(service)
+                .with_name(name)
+                .to_owned();
+
+            if let Some(filter) = filter {
+                toolbox.with_filter(filter.clone());
+            }
+
+            services.push(toolbox);
+        }
+        McpServer::Http {
+            name,
+            url,
+            headers,
+            filter,
+        } => {
+            let client = reqwest::Client::builder()
+                .default_headers(expand_headers(headers.as_ref())?)
+                .build()
+                .context("Failed to build HTTP client for streamable HTTP MCP transport")?;
+
+            let transport = StreamableHttpClientTransport::with_client(client, url.clone());
+
+            let service = kwaak_client_info().serve(transport).await?;
+
+            let mut toolbox = McpToolbox::from_running_service(service)
+                .with_name(name)
+                .to_owned();
+
+            if let Some(filter) = filter {
+                toolbox.with_filter(filter.clone());
+            }
+
+            services.push(toolbox);
         }
     }
-    Ok(services)
+
+    Ok(())
 }
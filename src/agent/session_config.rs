@@ -0,0 +1,96 @@
+//! Declarative per-session configuration, loaded from `.kwaak/session.toml` if present.
+//!
+//! Lets a session's agent configuration, edit mode, tool allow/deny list, MCP servers, and
+//! initial-context override be checked into the repo instead of relying solely on global
+//! config.
+
+use std::path::Path;
+
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+
+use crate::config::{self, AgentEditMode, mcp::McpServer};
+
+pub const SESSION_CONFIG_PATH: &str = ".kwaak/session.toml";
+
+/// Session-scoped configuration, merged over `repository.config()` when a session starts. Every
+/// field is optional: an unset field falls through to the global repository config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub agent: Option<config::SupportedAgentConfigurations>,
+    pub agent_edit_mode: Option<AgentEditMode>,
+    pub allowed_tools: Option<Vec<String>>,
+    pub denied_tools: Option<Vec<String>>,
+    pub mcp: Option<Vec<McpServer>>,
+    pub initial_context: Option<String>,
+}
+
+impl SessionConfig {
+    /// Loads `.kwaak/session.toml` relative to `repository_root`, if it exists. Returns the
+    /// default (fully-deferring-to-global-config) value if the file is absent.
+    pub fn load(repository_root: &Path) -> Result<Self> {
+        let path = repository_root.join(SESSION_CONFIG_PATH);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        toml::from_str(&raw).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Whether `tool_name` may be used under this session config: it must be on the allow-list
+    /// (if one is set) and must not be on the deny-list.
+    #[must_use]
+    pub fn allows_tool(&self, tool_name: &str) -> bool {
+        if let Some(allowed) = &self.allowed_tools {
+            if !allowed.iter().any(|name| name == tool_name) {
+                return false;
+            }
+        }
+
+        !self
+            .denied_tools
+            .as_ref()
+            .is_some_and(|denied| denied.iter().any(|name| name == tool_name))
+    }
+
+    #[must_use]
+    pub fn with_agent(mut self, agent: config::SupportedAgentConfigurations) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    #[must_use]
+    pub fn with_agent_edit_mode(mut self, agent_edit_mode: AgentEditMode) -> Self {
+        self.agent_edit_mode = Some(agent_edit_mode);
+        self
+    }
+
+    #[must_use]
+    pub fn with_allowed_tools(mut self, allowed_tools: Vec<String>) -> Self {
+        self.allowed_tools = Some(allowed_tools);
+        self
+    }
+
+    #[must_use]
+    pub fn with_denied_tools(mut self, denied_tools: Vec<String>) -> Self {
+        self.denied_tools = Some(denied_tools);
+        self
+    }
+
+    #[must_use]
+    pub fn with_mcp(mut self, mcp: Vec<McpServer>) -> Self {
+        self.mcp = Some(mcp);
+        self
+    }
+
+    #[must_use]
+    pub fn with_initial_context(mut self, initial_context: impl Into<String>) -> Self {
+        self.initial_context = Some(initial_context.into());
+        self
+    }
+}
@@ -0,0 +1,165 @@
+//! Repository- and session-level configuration types.
+//!
+//! `RepositoryConfig` is what `Repository::config()` hands back; a session's own
+//! `.kwaak/session.toml` (see [`crate::agent::session_config::SessionConfig`]) can override
+//! individual fields of it for the duration of one session.
+
+pub mod mcp;
+
+use serde::Deserialize;
+
+pub use mcp::McpServer;
+
+/// Which agent architecture a session should build. Matched exhaustively in
+/// `SessionBuilder::start_inner`, so adding a variant here and wiring it up there always go
+/// together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupportedAgentConfigurations {
+    /// A single coding agent, run directly against the user's queries.
+    Coding,
+    /// A planning agent that breaks the task into a dependency graph of subtasks, each dispatched
+    /// to its own coding agent via the `plan_and_execute` tool.
+    PlanAct,
+    /// A single coding agent driven autonomously through `gated::GatedRunner`'s
+    /// `Editing -> Validating -> Advancing -> Done` loop until tests pass.
+    Gated,
+}
+
+/// How the coding agent is allowed to edit files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentEditMode {
+    /// Rewrite whole files.
+    Whole,
+    /// Edit by line number (replace/add lines).
+    Line,
+    /// Edit via unified-diff style patches.
+    Patch,
+}
+
+/// A config value that should never be printed or logged in full.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    #[must_use]
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+/// Test/coverage commands run by the `run_tests`/`run_coverage` tools, and by gated mode's
+/// validation step.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CommandsConfig {
+    pub test: Option<String>,
+    pub coverage: Option<String>,
+}
+
+/// LLM backoff/retry behavior, passed to provider model constructors.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Backoff {
+    pub initial_delay_ms: u64,
+    pub max_retries: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 500,
+            max_retries: 5,
+        }
+    }
+}
+
+/// The fast/cheap model used for indexing-adjacent work (chat renaming, branch naming, context
+/// retrieval), as opposed to the main coding agent's model.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IndexingProvider {
+    pub api_key: Option<Secret>,
+    pub model: Option<String>,
+}
+
+impl IndexingProvider {
+    /// Entry point for the cheap/fast model used for chat renaming, branch naming, and context
+    /// retrieval. No model is built here: this returns an error in every case, and the backoff
+    /// is not used.
+    pub fn get_simple_prompt_model(
+        &self,
+        _backoff: Backoff,
+    ) -> anyhow::Result<Box<dyn swiftide::traits::SimplePrompt>> {
+        anyhow::bail!("No indexing provider configured")
+    }
+}
+
+/// Per-repository configuration, loaded from `kwaak.toml` at the repository root.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RepositoryConfig {
+    pub backoff: Backoff,
+    pub indexing_provider: IndexingProvider,
+    pub agent: SupportedAgentConfigurations,
+    pub agent_edit_mode: AgentEditMode,
+    pub tavily_api_key: Option<Secret>,
+    pub commands: CommandsConfig,
+    pub disabled_tools: Vec<String>,
+    pub github_api_key: Option<Secret>,
+    pub mcp: Option<Vec<McpServer>>,
+
+    /// Max number of subtasks `plan_and_execute` runs concurrently.
+    pub plan_max_concurrency: Option<usize>,
+    /// Max number of `Editing -> Validating` attempts gated mode makes before giving up.
+    pub gated_max_retries: Option<usize>,
+}
+
+impl Default for SupportedAgentConfigurations {
+    fn default() -> Self {
+        Self::Coding
+    }
+}
+
+impl Default for AgentEditMode {
+    fn default() -> Self {
+        Self::Whole
+    }
+}
+
+impl RepositoryConfig {
+    #[must_use]
+    pub fn indexing_provider(&self) -> &IndexingProvider {
+        &self.indexing_provider
+    }
+
+    #[must_use]
+    pub fn is_github_enabled(&self) -> bool {
+        self.github_api_key.is_some()
+    }
+
+    #[must_use]
+    pub fn disabled_tools(&self) -> &[String] {
+        &self.disabled_tools
+    }
+
+    /// Defaults to 3 concurrent subtasks when unset.
+    #[must_use]
+    pub fn plan_max_concurrency(&self) -> usize {
+        self.plan_max_concurrency.unwrap_or(3)
+    }
+
+    /// Defaults to 3 attempts when unset.
+    #[must_use]
+    pub fn gated_max_retries(&self) -> usize {
+        self.gated_max_retries.unwrap_or(3)
+    }
+}
@@ -0,0 +1,43 @@
+//! MCP server definitions, as configured under `[[mcp]]` in repository/session config.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use super::Secret;
+
+/// A single configured MCP server. Each variant corresponds to one of the transports
+/// `start_one_mcp_toolbox` knows how to bring up.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "transport", rename_all = "snake_case")]
+pub enum McpServer {
+    /// A locally spawned MCP server, communicating over stdio.
+    SubProcess {
+        name: String,
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        filter: Option<Vec<String>>,
+        #[serde(default)]
+        env: Option<HashMap<String, Secret>>,
+    },
+    /// A remote MCP server speaking the (legacy) HTTP+SSE transport.
+    Sse {
+        name: String,
+        url: String,
+        #[serde(default)]
+        filter: Option<Vec<String>>,
+        #[serde(default)]
+        headers: Option<HashMap<String, Secret>>,
+    },
+    /// A remote MCP server speaking the streamable HTTP transport.
+    Http {
+        name: String,
+        url: String,
+        #[serde(default)]
+        filter: Option<Vec<String>>,
+        #[serde(default)]
+        headers: Option<HashMap<String, Secret>>,
+    },
+}